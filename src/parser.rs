@@ -0,0 +1,276 @@
+//! Parser-combinator grammar for the almanac, consuming the whole input at
+//! once. Parse failures carry the byte offset / line where the grammar broke
+//! instead of panicking, so callers get an `anyhow::Result` rather than an
+//! unwind.
+
+use std::collections::HashMap;
+use std::ops::Range;
+use std::str::FromStr;
+
+use anyhow::{bail, Result};
+use nom::{
+    bytes::complete::tag,
+    character::complete::{alpha1, char, line_ending, multispace0, space1, u64 as parse_u64},
+    combinator::map,
+    combinator::map_res,
+    multi::{many1, separated_list1},
+    sequence::{separated_pair, terminated, tuple},
+    IResult,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Category {
+    Seed,
+    Soil,
+    Fertilizer,
+    Water,
+    Light,
+    Temperature,
+    Humidity,
+    Location,
+}
+
+impl FromStr for Category {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "seed" => Category::Seed,
+            "soil" => Category::Soil,
+            "fertilizer" => Category::Fertilizer,
+            "water" => Category::Water,
+            "light" => Category::Light,
+            "temperature" => Category::Temperature,
+            "humidity" => Category::Humidity,
+            "location" => Category::Location,
+            other => bail!("unknown category: {other}"),
+        })
+    }
+}
+
+pub struct AlmanacRange {
+    source_start: usize,
+    destination_start: usize,
+    range_length: usize,
+}
+
+impl AlmanacRange {
+    fn get_destination(&self, source: usize) -> Option<usize> {
+        if source < self.source_start || source >= self.source_start + self.range_length {
+            None
+        } else {
+            Some(self.destination_start + (source - self.source_start))
+        }
+    }
+
+    fn get_source(&self, destination: usize) -> Option<usize> {
+        if destination < self.destination_start
+            || destination >= self.destination_start + self.range_length
+        {
+            None
+        } else {
+            Some(self.source_start + (destination - self.destination_start))
+        }
+    }
+}
+
+pub struct AlmanacRanges {
+    ranges: Vec<AlmanacRange>,
+}
+
+impl AlmanacRanges {
+    fn from_vec(mut ranges: Vec<AlmanacRange>) -> Self {
+        ranges.sort_by_key(|r| r.source_start);
+        AlmanacRanges { ranges }
+    }
+
+    fn get_destination(&self, source: usize) -> usize {
+        self.ranges
+            .iter()
+            .filter_map(|r| r.get_destination(source))
+            .next()
+            .unwrap_or(source)
+    }
+
+    /// Inverse of `get_destination`: given a value in destination space, find
+    /// the source that maps to it. Unmapped values are the identity.
+    fn get_source(&self, destination: usize) -> usize {
+        self.ranges
+            .iter()
+            .filter_map(|r| r.get_source(destination))
+            .next()
+            .unwrap_or(destination)
+    }
+
+    /// Map a set of input ranges through this set of mappings, splitting each
+    /// input range at mapping boundaries. The entries are kept sorted by
+    /// `source_start` (see `from_vec`), so we can walk a cursor across each
+    /// input range and peel off one overlapping or gap sub-range at a time.
+    /// Every input integer lands in exactly one output range; the results are
+    /// neither sorted nor merged, which is fine since callers only want the
+    /// minimum start.
+    fn map_ranges(&self, input: Vec<Range<usize>>) -> Vec<Range<usize>> {
+        let mut output = Vec::new();
+        for range in input {
+            let mut cursor = range.start;
+            let hi = range.end;
+            while cursor < hi {
+                // The first mapping whose source interval hasn't ended before
+                // the cursor either covers the cursor or lies ahead of it.
+                let next = self
+                    .ranges
+                    .iter()
+                    .find(|r| cursor < r.source_start + r.range_length);
+                match next {
+                    // Cursor sits inside this mapping: emit the overlap shifted
+                    // into destination space and skip past it.
+                    Some(r) if cursor >= r.source_start => {
+                        let overlap_end = (r.source_start + r.range_length).min(hi);
+                        let start = r.destination_start + (cursor - r.source_start);
+                        let end = r.destination_start + (overlap_end - r.source_start);
+                        output.push(start..end);
+                        cursor = overlap_end;
+                    }
+                    // Cursor lies in the gap before the next mapping: identity.
+                    Some(r) => {
+                        let gap_end = r.source_start.min(hi);
+                        output.push(cursor..gap_end);
+                        cursor = gap_end;
+                    }
+                    // Cursor is past the last mapping: identity to the end.
+                    None => {
+                        output.push(cursor..hi);
+                        cursor = hi;
+                    }
+                }
+            }
+        }
+        output
+    }
+}
+
+pub struct Almanac {
+    pub seeds: Vec<usize>,
+    /// Each category points at the category it converts into and the mapping
+    /// to get there, so the conversion order comes from the parsed headers
+    /// rather than the section order.
+    pub maps: HashMap<Category, (Category, AlmanacRanges)>,
+}
+
+impl Almanac {
+    /// Walk the conversion graph from `start` to `goal`, applying one edge's
+    /// mapping at a time.
+    pub fn convert(&self, start: Category, goal: Category, value: usize) -> usize {
+        let mut category = start;
+        let mut value = value;
+        while category != goal {
+            let (next, ranges) = &self.maps[&category];
+            value = ranges.get_destination(value);
+            category = *next;
+        }
+        value
+    }
+
+    /// Walk the conversion graph forwards from `start` to `goal`, splitting the
+    /// ranges at each edge's mapping boundaries.
+    pub fn convert_ranges(
+        &self,
+        start: Category,
+        goal: Category,
+        input: Vec<Range<usize>>,
+    ) -> Vec<Range<usize>> {
+        let mut category = start;
+        let mut ranges = input;
+        while category != goal {
+            let (next, map) = &self.maps[&category];
+            ranges = map.map_ranges(ranges);
+            category = *next;
+        }
+        ranges
+    }
+
+    /// Walk the conversion graph backwards from `start` to `goal` by following
+    /// each edge in reverse, so a single location maps back to its seed.
+    pub fn convert_source(&self, start: Category, goal: Category, value: usize) -> usize {
+        let mut category = start;
+        let mut value = value;
+        while category != goal {
+            let (source, map) = self
+                .maps
+                .iter()
+                .find_map(|(src, (dst, map))| (*dst == category).then_some((*src, map)))
+                .expect("no incoming edge");
+            value = map.get_source(value);
+            category = source;
+        }
+        value
+    }
+}
+
+fn category(input: &str) -> IResult<&str, Category> {
+    map_res(alpha1, Category::from_str)(input)
+}
+
+fn number(input: &str) -> IResult<&str, usize> {
+    map(parse_u64, |n| n as usize)(input)
+}
+
+fn triple(input: &str) -> IResult<&str, AlmanacRange> {
+    map(
+        tuple((number, space1, number, space1, number)),
+        |(destination_start, _, source_start, _, range_length)| AlmanacRange {
+            destination_start,
+            source_start,
+            range_length,
+        },
+    )(input)
+}
+
+/// An `X-to-Y map:` header followed by one or more triples of numbers.
+fn block(input: &str) -> IResult<&str, (Category, Category, AlmanacRanges)> {
+    let (input, (from, to)) = terminated(
+        separated_pair(category, tag("-to-"), category),
+        tuple((char(' '), tag("map:"), line_ending)),
+    )(input)?;
+    let (input, rows) = separated_list1(line_ending, triple)(input)?;
+    Ok((input, (from, to, AlmanacRanges::from_vec(rows))))
+}
+
+fn almanac(input: &str) -> IResult<&str, Almanac> {
+    let (input, _) = tuple((tag("seeds:"), space1))(input)?;
+    let (input, seeds) = separated_list1(space1, number)(input)?;
+    let (input, _) = multispace0(input)?;
+    let (input, blocks) = separated_list1(many1(line_ending), block)(input)?;
+    let maps = blocks
+        .into_iter()
+        .map(|(from, to, ranges)| (from, (to, ranges)))
+        .collect();
+    Ok((input, Almanac { seeds, maps }))
+}
+
+/// Parse a complete almanac from a string slice, reporting the byte offset and
+/// line of the first grammar failure rather than panicking.
+pub fn parse(input: &str) -> Result<Almanac> {
+    match almanac(input) {
+        Ok((rest, almanac)) if rest.trim().is_empty() => Ok(almanac),
+        Ok((rest, _)) => {
+            let rest = rest.trim_start();
+            let offset = input.len() - rest.len();
+            let line = input[..offset].matches('\n').count() + 1;
+            bail!(
+                "parse error at byte {offset} (line {line}): unexpected input near {:?}",
+                rest.chars().take(16).collect::<String>()
+            )
+        }
+        Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => {
+            let offset = input.len() - e.input.len();
+            let line = input[..offset].matches('\n').count() + 1;
+            bail!(
+                "parse error at byte {offset} (line {line}): unexpected input near {:?} ({:?})",
+                e.input.chars().take(16).collect::<String>(),
+                e.code
+            )
+        }
+        Err(nom::Err::Incomplete(_)) => bail!("incomplete input"),
+    }
+}